@@ -1,7 +1,11 @@
 use js_sys::{Array, Object, Reflect};
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use wasm_bindgen::closure::Closure;
 use wasm_bindgen::prelude::*;
-use web_sys::CanvasRenderingContext2d;
+use wasm_bindgen::JsCast;
+use web_sys::{Blob, CanvasRenderingContext2d, FileReader, OffscreenCanvasRenderingContext2d};
 
 #[wasm_bindgen]
 extern "C" {
@@ -54,12 +58,87 @@ struct Row {
     cells: HashMap<String, CellValue>,
 }
 
+// 将一个 JS 行对象解析为 Row，按 col_0..col_{columns-1} 读取
+fn js_value_to_row(value: &JsValue, columns: u32) -> Row {
+    let mut cells = HashMap::new();
+    for col in 0..columns {
+        let key = format!("col_{}", col);
+        let js_key = JsValue::from_str(&key);
+        if let Ok(cell_value) = Reflect::get(value, &js_key) {
+            if let Some(text) = cell_value.as_string() {
+                cells.insert(key, text);
+            }
+        }
+    }
+    Row { cells }
+}
+
+// 将一个 JS 数组解析为一批 Row
+fn js_array_to_rows(data: &JsValue, columns: u32) -> Vec<Row> {
+    Array::from(data)
+        .iter()
+        .map(|value| js_value_to_row(&value, columns))
+        .collect()
+}
+
+// 将一行 Row 转换为 JS 对象，供渲染器/宿主读取
+fn row_to_js_object(row: &Row) -> Object {
+    let js_row = Object::new();
+    for (key, value) in &row.cells {
+        Reflect::set(&js_row, &JsValue::from_str(key), &JsValue::from_str(value)).unwrap();
+    }
+    js_row
+}
+
+// 比较两个单元格的值：两边都能解析为数字时按数值比较，否则按字典序比较
+fn compare_cell_values(a: &str, b: &str) -> std::cmp::Ordering {
+    match (a.parse::<f64>(), b.parse::<f64>()) {
+        (Ok(x), Ok(y)) => x.partial_cmp(&y).unwrap_or(std::cmp::Ordering::Equal),
+        _ => a.cmp(b),
+    }
+}
+
+// 一次矩形选区，anchor 是拖拽起点，focus 是当前指针所在的单元格，均为可视行/列坐标
+#[derive(Clone, Copy)]
+struct Selection {
+    anchor: (u32, u32),
+    focus: (u32, u32),
+}
+
+impl Selection {
+    // 归一化为 (row_min, row_max, col_min, col_max)
+    fn normalized(&self) -> (u32, u32, u32, u32) {
+        let (r1, c1) = self.anchor;
+        let (r2, c2) = self.focus;
+        (r1.min(r2), r1.max(r2), c1.min(c2), c1.max(c2))
+    }
+}
+
 // 表格数据管理器
 #[wasm_bindgen]
 pub struct TableManager {
     renderer: TableRenderer,
-    data_cache: HashMap<u32, Vec<Row>>,
+    // 已加载段的缓存，使用 Rc<RefCell<>> 以便异步段加载器的回调能在段加载完成后写回
+    data_cache: Rc<RefCell<HashMap<u32, Vec<Row>>>>,
+    // 正在等待异步加载器返回的段，避免重复发起请求
+    pending_segments: Rc<RefCell<HashSet<u32>>>,
     segment_size: u32,
+    // 通过 set_rows 注入的完整数据集（与 segment_loader 互斥）
+    local_rows: Vec<Row>,
+    // 通过 set_segment_loader 注入的按需分段加载回调
+    segment_loader: Option<js_sys::Function>,
+    // 可视行 -> 源数据行的索引排列，None 表示未排序（按源顺序显示）
+    sort_order: Option<Vec<u32>>,
+    // sort_order 的反向索引：源数据行 -> 可视行，随 sort_order 一起重建/失效，避免逐次线性扫描
+    visual_row_by_source: Option<Vec<u32>>,
+    // 当前的矩形选区（可视坐标），None 表示未选中
+    selection: Option<Selection>,
+    // 当前搜索命中的单元格坐标 (source_row, col)，按行优先排序
+    search_matches: Vec<(u32, u32)>,
+    // 当前高亮的命中项在 search_matches 中的下标
+    current_match: Option<usize>,
+    // 每次 render_content 调用后，用归一化的滚动进度 (x, y) 通知宿主
+    scroll_progress_callback: Option<js_sys::Function>,
 }
 
 #[wasm_bindgen]
@@ -69,9 +148,329 @@ impl TableManager {
         log("创建表格管理器");
         TableManager {
             renderer: TableRenderer::new(config),
-            data_cache: HashMap::new(),
+            data_cache: Rc::new(RefCell::new(HashMap::new())),
+            pending_segments: Rc::new(RefCell::new(HashSet::new())),
             segment_size,
+            local_rows: Vec::new(),
+            segment_loader: None,
+            sort_order: None,
+            visual_row_by_source: None,
+            selection: None,
+            search_matches: Vec::new(),
+            current_match: None,
+            scroll_progress_callback: None,
+        }
+    }
+
+    // 注册滚动进度回调：(progress_x: number, progress_y: number) => void，范围 0.0~1.0
+    #[wasm_bindgen]
+    pub fn set_on_scroll_progress(&mut self, callback: js_sys::Function) {
+        self.scroll_progress_callback = Some(callback);
+    }
+
+    // 命中测试：根据内容区坐标 (x, y) 与当前滚动偏移，返回其下方的 [row, col]（可视坐标），无命中时返回 null
+    #[wasm_bindgen]
+    pub fn hit_test(&self, x: f64, y: f64, scroll_left: f64, scroll_top: f64) -> JsValue {
+        let abs_x = x + scroll_left;
+        let abs_y = y + scroll_top;
+
+        let total_content_height =
+            self.renderer.get_total_height() - self.renderer.config.header_height;
+        if abs_x < 0.0
+            || abs_y < 0.0
+            || abs_x >= self.renderer.get_total_width()
+            || abs_y >= total_content_height
+        {
+            return JsValue::NULL;
+        }
+
+        let row = self.renderer.row_at_offset(abs_y);
+        let col = self.renderer.col_at_offset(abs_x);
+
+        let result = Array::new();
+        result.push(&JsValue::from(row));
+        result.push(&JsValue::from(col));
+        result.into()
+    }
+
+    // 开始一次拖拽选区，anchor 与 focus 都落在 (row, col)
+    #[wasm_bindgen]
+    pub fn start_selection(&mut self, row: u32, col: u32) {
+        self.selection = Some(Selection {
+            anchor: (row, col),
+            focus: (row, col),
+        });
+        self.sync_selection_to_renderer();
+    }
+
+    // 拖拽过程中更新选区的 focus 端点
+    #[wasm_bindgen]
+    pub fn update_selection(&mut self, row: u32, col: u32) {
+        if let Some(selection) = &mut self.selection {
+            selection.focus = (row, col);
+        }
+        self.sync_selection_to_renderer();
+    }
+
+    // 清除当前选区
+    #[wasm_bindgen]
+    pub fn clear_selection(&mut self) {
+        self.selection = None;
+        self.renderer.clear_selection();
+    }
+
+    // 把选区的归一化矩形同步给渲染器，供高亮绘制使用
+    fn sync_selection_to_renderer(&mut self) {
+        match self.selection {
+            Some(selection) => self.renderer.set_selection(selection.normalized()),
+            None => self.renderer.clear_selection(),
+        }
+    }
+
+    // 获取当前选区内的单元格文本，返回一个二维数组（行优先），供宿主实现复制
+    #[wasm_bindgen]
+    pub fn get_selected_values(&mut self) -> JsValue {
+        let Some(selection) = self.selection else {
+            return JsValue::NULL;
+        };
+        let (row_min, row_max, col_min, col_max) = selection.normalized();
+
+        let result = Array::new();
+        for row in row_min..=row_max {
+            let source_row = match &self.sort_order {
+                Some(order) => *order.get(row as usize).unwrap_or(&row),
+                None => row,
+            };
+            let segment_idx = source_row / self.segment_size;
+            let segment = self.get_or_load_segment(segment_idx);
+            let index_in_segment = (source_row % self.segment_size) as usize;
+
+            let js_row = Array::new();
+            for col in col_min..=col_max {
+                let key = format!("col_{}", col);
+                let value = segment
+                    .get(index_in_segment)
+                    .and_then(|r| r.cells.get(&key))
+                    .cloned()
+                    .unwrap_or_default();
+                js_row.push(&JsValue::from_str(&value));
+            }
+            result.push(&js_row);
+        }
+
+        result.into()
+    }
+
+    // 按某一列排序：本地数据集直接排序，懒加载模式下会先 await 取全部段，再整体排序，
+    // 避免在尚未取到的段上构建出长度不对的 sort_order
+    #[wasm_bindgen]
+    pub async fn sort_by_column(&mut self, col: u32, ascending: bool) -> Result<(), JsValue> {
+        self.materialize_all_segments().await?;
+
+        let key = format!("col_{}", col);
+        let rows = self.collect_all_rows();
+
+        let mut indices: Vec<u32> = (0..rows.len() as u32).collect();
+        indices.sort_by(|&a, &b| {
+            let va = rows[a as usize]
+                .cells
+                .get(&key)
+                .map(|s| s.as_str())
+                .unwrap_or("");
+            let vb = rows[b as usize]
+                .cells
+                .get(&key)
+                .map(|s| s.as_str())
+                .unwrap_or("");
+            let ord = compare_cell_values(va, vb);
+            if ascending {
+                ord
+            } else {
+                ord.reverse()
+            }
+        });
+
+        // 反向索引：source_to_visual_row 据此做 O(1) 查找，而不是每次线性扫描 indices
+        let mut visual_row_by_source = vec![0u32; indices.len()];
+        for (visual_row, &source_row) in indices.iter().enumerate() {
+            visual_row_by_source[source_row as usize] = visual_row as u32;
+        }
+
+        self.sort_order = Some(indices);
+        self.visual_row_by_source = Some(visual_row_by_source);
+        self.renderer.set_sort_indicator(col, ascending);
+        Ok(())
+    }
+
+    // 收集当前已知的全部行：本地数据集直接返回，懒加载模式下按段拉取并拼接。
+    // 注意：分段加载模式下这只会返回已缓存的段和同步 segment_loader 能立即返回的段，异步 segment_loader 尚未 resolve 的段会被跳过——
+    // 需要完整数据集的调用方（排序、搜索）应先调用 materialize_all_segments
+    fn collect_all_rows(&mut self) -> Vec<Row> {
+        if !self.local_rows.is_empty() {
+            return self.local_rows.clone();
+        }
+
+        let segment_size = self.segment_size.max(1);
+        let segment_count = self.renderer.config.rows.div_ceil(segment_size);
+        let mut rows = Vec::with_capacity(self.renderer.config.rows as usize);
+        for segment_idx in 0..segment_count {
+            rows.extend(self.get_or_load_segment(segment_idx));
+        }
+        rows
+    }
+
+    // 确保懒加载模式下的全部数据段都已取到本地缓存：对尚未缓存的段调用 segment_loader 并 await，
+    // 供排序/搜索等需要完整数据集的一次性操作在读取前调用；本地数据集或未设置 loader 时直接返回。
+    // 这里直接写入缓存而不经过 insert_segment，以免 clean_cache 在取后面的段时淘汰前面已取到的段
+    async fn materialize_all_segments(&mut self) -> Result<(), JsValue> {
+        let Some(loader) = self.segment_loader.clone() else {
+            return Ok(());
+        };
+        if !self.local_rows.is_empty() {
+            return Ok(());
+        }
+
+        let columns = self.renderer.config.columns;
+        let segment_size = self.segment_size.max(1);
+        let segment_count = self.renderer.config.rows.div_ceil(segment_size);
+
+        for segment_idx in 0..segment_count {
+            if self.data_cache.borrow().contains_key(&segment_idx) {
+                continue;
+            }
+
+            let result = loader.call1(&JsValue::NULL, &JsValue::from(segment_idx))?;
+            let rows = if let Some(promise) = result.dyn_ref::<js_sys::Promise>() {
+                let resolved = wasm_bindgen_futures::JsFuture::from(promise.clone()).await?;
+                js_array_to_rows(&resolved, columns)
+            } else {
+                js_array_to_rows(&result, columns)
+            };
+            self.data_cache.borrow_mut().insert(segment_idx, rows);
+            self.pending_segments.borrow_mut().remove(&segment_idx);
+        }
+
+        Ok(())
+    }
+
+    // 用正则在全部数据中搜索，记录行优先排序的匹配坐标。返回匹配总数。
+    // 懒加载模式下会先 await 取全部段再搜索，避免对尚未 resolve 的段漏报匹配
+    #[wasm_bindgen]
+    pub async fn search(
+        &mut self,
+        pattern: &str,
+        case_insensitive: bool,
+    ) -> Result<usize, JsValue> {
+        let regex = match regex::RegexBuilder::new(pattern)
+            .case_insensitive(case_insensitive)
+            .build()
+        {
+            Ok(regex) => regex,
+            Err(_) => {
+                self.search_matches.clear();
+                self.current_match = None;
+                self.sync_search_to_renderer();
+                return Ok(0);
+            }
+        };
+
+        self.materialize_all_segments().await?;
+        let rows = self.collect_all_rows();
+        let columns = self.renderer.config.columns;
+        let keys: Vec<String> = (0..columns).map(|c| format!("col_{}", c)).collect();
+
+        let mut matches = Vec::new();
+        for (row_idx, row) in rows.iter().enumerate() {
+            for (col, key) in keys.iter().enumerate() {
+                if let Some(value) = row.cells.get(key) {
+                    if regex.is_match(value) {
+                        matches.push((row_idx as u32, col as u32));
+                    }
+                }
+            }
+        }
+
+        self.search_matches = matches;
+        self.current_match = if self.search_matches.is_empty() {
+            None
+        } else {
+            Some(0)
+        };
+        self.sync_search_to_renderer();
+        Ok(self.search_matches.len())
+    }
+
+    // 当前匹配总数
+    #[wasm_bindgen]
+    pub fn match_count(&self) -> usize {
+        self.search_matches.len()
+    }
+
+    // 跳到下一个匹配，返回需要滚动到的 [row, col]（可视坐标），无匹配时返回 null
+    #[wasm_bindgen]
+    pub fn next_match(&mut self) -> JsValue {
+        if self.search_matches.is_empty() {
+            return JsValue::NULL;
         }
+        let next = match self.current_match {
+            Some(i) => (i + 1) % self.search_matches.len(),
+            None => 0,
+        };
+        self.current_match = Some(next);
+        self.sync_search_to_renderer();
+        self.match_coordinate(next)
+    }
+
+    // 跳到上一个匹配，返回需要滚动到的 [row, col]（可视坐标），无匹配时返回 null
+    #[wasm_bindgen]
+    pub fn prev_match(&mut self) -> JsValue {
+        if self.search_matches.is_empty() {
+            return JsValue::NULL;
+        }
+        let prev = match self.current_match {
+            Some(0) | None => self.search_matches.len() - 1,
+            Some(i) => i - 1,
+        };
+        self.current_match = Some(prev);
+        self.sync_search_to_renderer();
+        self.match_coordinate(prev)
+    }
+
+    // 取出第 index 个匹配，转换为可视坐标 [row, col]
+    fn match_coordinate(&self, index: usize) -> JsValue {
+        let (source_row, col) = self.search_matches[index];
+        let visual_row = self.source_to_visual_row(source_row);
+        let result = Array::new();
+        result.push(&JsValue::from(visual_row));
+        result.push(&JsValue::from(col));
+        result.into()
+    }
+
+    // 把源数据行号转换为当前排序下的可视行号；未排序时两者相同。
+    // 通过 sort_by_column 构建的反向索引做 O(1) 查找，而不是在 sort_order 里线性扫描
+    fn source_to_visual_row(&self, source_row: u32) -> u32 {
+        match &self.visual_row_by_source {
+            Some(reverse) => reverse
+                .get(source_row as usize)
+                .copied()
+                .unwrap_or(source_row),
+            None => source_row,
+        }
+    }
+
+    // 把命中集合与当前高亮项转换为可视坐标后同步给渲染器
+    fn sync_search_to_renderer(&mut self) {
+        let visual_matches: HashSet<(u32, u32)> = self
+            .search_matches
+            .iter()
+            .map(|&(row, col)| (self.source_to_visual_row(row), col))
+            .collect();
+        let active_match = self.current_match.map(|i| {
+            let (row, col) = self.search_matches[i];
+            (self.source_to_visual_row(row), col)
+        });
+        self.renderer
+            .set_search_matches(visual_matches, active_match);
     }
 
     // 获取表格总宽度
@@ -86,20 +485,163 @@ impl TableManager {
         self.renderer.get_total_height()
     }
 
+    // 注入完整数据集，替代懒加载段；会清空已有缓存并切换到本地数据模式
+    #[wasm_bindgen]
+    pub fn set_rows(&mut self, data: JsValue) {
+        self.local_rows = js_array_to_rows(&data, self.renderer.config.columns);
+        self.renderer.config.rows = self.local_rows.len() as u32;
+        self.segment_loader = None;
+        self.data_cache.borrow_mut().clear();
+        self.pending_segments.borrow_mut().clear();
+        self.sort_order = None;
+        self.visual_row_by_source = None;
+        self.renderer.clear_sort_indicator();
+    }
+
+    // 注册按段异步加载数据的回调：(segment_index: number) => rows[] | Promise<rows[]>
+    #[wasm_bindgen]
+    pub fn set_segment_loader(&mut self, callback: js_sys::Function) {
+        self.segment_loader = Some(callback);
+        self.local_rows.clear();
+        self.data_cache.borrow_mut().clear();
+        self.pending_segments.borrow_mut().clear();
+        self.sort_order = None;
+        self.visual_row_by_source = None;
+        self.renderer.clear_sort_indicator();
+    }
+
+    // 在末尾追加若干行，已缓存的段无需整体失效，只需重建最后一个可能不完整的段
+    // 仅适用于 set_rows 注入的本地数据集；分段加载模式下数据由宿主的 segment_loader 管理，无法在此追加
+    #[wasm_bindgen]
+    pub fn append_rows(&mut self, data: JsValue) {
+        if self.segment_loader.is_some() {
+            log("append_rows 在分段加载模式下不可用，请通过 segment_loader 自行管理新增数据");
+            return;
+        }
+
+        let mut new_rows = js_array_to_rows(&data, self.renderer.config.columns);
+        if new_rows.is_empty() {
+            return;
+        }
+
+        if let Some(last_segment) = self
+            .local_rows
+            .len()
+            .checked_sub(1)
+            .map(|i| i as u32 / self.segment_size)
+        {
+            self.data_cache.borrow_mut().remove(&last_segment);
+        }
+
+        self.local_rows.append(&mut new_rows);
+        self.renderer.config.rows = self.local_rows.len() as u32;
+        self.sort_order = None;
+        self.visual_row_by_source = None;
+        self.renderer.clear_sort_indicator();
+    }
+
+    // 在开头插入若干行，并将缓存的段索引整体前移（段大小的整数倍时可直接平移，否则只能整体失效）
+    // 仅适用于 set_rows 注入的本地数据集；分段加载模式下数据由宿主的 segment_loader 管理，无法在此插入
+    #[wasm_bindgen]
+    pub fn prepend_rows(&mut self, data: JsValue) {
+        if self.segment_loader.is_some() {
+            log("prepend_rows 在分段加载模式下不可用，请通过 segment_loader 自行管理新增数据");
+            return;
+        }
+
+        let mut new_rows = js_array_to_rows(&data, self.renderer.config.columns);
+        if new_rows.is_empty() {
+            return;
+        }
+        let new_count = new_rows.len() as u32;
+
+        new_rows.extend(std::mem::take(&mut self.local_rows));
+        self.local_rows = new_rows;
+        self.renderer.config.rows = self.local_rows.len() as u32;
+
+        let mut cache = self.data_cache.borrow_mut();
+        if new_count.is_multiple_of(self.segment_size) {
+            let shift = new_count / self.segment_size;
+            let shifted: HashMap<u32, Vec<Row>> = cache
+                .drain()
+                .map(|(idx, rows)| (idx + shift, rows))
+                .collect();
+            *cache = shifted;
+        } else {
+            cache.clear();
+        }
+        drop(cache);
+        self.sort_order = None;
+        self.visual_row_by_source = None;
+        self.renderer.clear_sort_indicator();
+    }
+
     // 渲染表头
     #[wasm_bindgen]
     pub fn render_header(&self, ctx: &CanvasRenderingContext2d, scroll_left: f64) {
-        // 计算可见区域中的起始/结束列索引
-        let start_col = (scroll_left / self.renderer.config.cell_width).floor() as u32;
-        let end_col = ((scroll_left + self.renderer.config.visible_width)
-            / self.renderer.config.cell_width)
-            .floor() as u32;
-        let end_col = end_col.min(self.renderer.config.columns - 1);
+        // 计算可见区域中的起始/结束列索引（对列宽累积偏移量做二分查找）
+        let start_col = self.renderer.col_at_offset(scroll_left);
+        let end_col = self
+            .renderer
+            .col_at_offset(scroll_left + self.renderer.config.visible_width);
 
         self.renderer
             .render_header(ctx, start_col, end_col, scroll_left);
     }
 
+    // 依据当前已加载的数据自动计算并应用每列宽度
+    #[wasm_bindgen]
+    pub fn auto_size_columns(&mut self, ctx: &CanvasRenderingContext2d) {
+        let rows = self.snapshot_rows_for_sizing();
+        let js_array = Array::new();
+        for row in &rows {
+            js_array.push(&row_to_js_object(row));
+        }
+        self.renderer.auto_size_columns(ctx, &js_array.into());
+    }
+
+    // 手动设置单列宽度
+    #[wasm_bindgen]
+    pub fn set_column_width(&mut self, col: u32, width: f64) {
+        self.renderer.set_column_width(col, width);
+    }
+
+    // 依据当前已加载的数据自动测量并应用每行高度，max_lines 限制单元格最多换行的行数
+    #[wasm_bindgen]
+    pub fn auto_size_rows(&mut self, ctx: &CanvasRenderingContext2d, max_lines: u32) {
+        let rows = self.snapshot_rows_for_sizing();
+        let js_array = Array::new();
+        for row in &rows {
+            js_array.push(&row_to_js_object(row));
+        }
+        self.renderer
+            .auto_size_rows(ctx, &js_array.into(), max_lines);
+    }
+
+    // 手动设置每行高度，长度应与当前行数一致
+    #[wasm_bindgen]
+    pub fn set_row_heights(&mut self, heights: Vec<f64>) {
+        self.renderer.set_row_heights(heights);
+    }
+
+    // 取消自定义行高，恢复为统一的 cell_height
+    #[wasm_bindgen]
+    pub fn clear_row_heights(&mut self) {
+        self.renderer.clear_row_heights();
+    }
+
+    // 为自动列宽取一份当前已知数据的快照：优先使用本地完整数据集，否则拼接已缓存的段
+    fn snapshot_rows_for_sizing(&self) -> Vec<Row> {
+        if !self.local_rows.is_empty() {
+            return self.local_rows.clone();
+        }
+
+        let cache = self.data_cache.borrow();
+        let mut keys: Vec<&u32> = cache.keys().collect();
+        keys.sort();
+        keys.into_iter().flat_map(|k| cache[k].clone()).collect()
+    }
+
     // 渲染内容区域
     #[wasm_bindgen]
     pub fn render_content(
@@ -108,24 +650,26 @@ impl TableManager {
         scroll_left: f64,
         scroll_top: f64,
     ) {
-        // 计算可见区域的行范围
-        let start_row = (scroll_top / self.renderer.config.cell_height).floor() as u32;
-        let visible_rows = (self.renderer.config.visible_height / self.renderer.config.cell_height)
-            .ceil() as u32
-            + 2;
-        let end_row = (start_row + visible_rows).min(self.renderer.config.rows - 1);
-
-        // 计算需要加载的数据段
-        // let start_segment = start_row / self.segment_size;
-        // let end_segment = end_row / self.segment_size;
-
-        // 准备可见数据
+        // 计算可见区域的行范围（对累积行偏移量数组做二分查找，支持可变行高），末尾多留一行缓冲
+        let start_row = self.renderer.row_at_offset(scroll_top);
+        let end_row = (self
+            .renderer
+            .row_at_offset(scroll_top + self.renderer.config.visible_height)
+            + 1)
+        .min(self.renderer.config.rows - 1);
+
+        // 准备可见数据：排序后，可视行号需要先通过 sort_order 映射回源数据行号
         let mut visible_data = Vec::new();
         for row_idx in start_row..=end_row {
+            let source_row = match &self.sort_order {
+                Some(order) => *order.get(row_idx as usize).unwrap_or(&row_idx),
+                None => row_idx,
+            };
+
             let segment_size = self.segment_size;
-            let segment_idx = row_idx / segment_size;
+            let segment_idx = source_row / segment_size;
             let segment = self.get_or_load_segment(segment_idx);
-            let index_in_segment = (row_idx % segment_size) as usize;
+            let index_in_segment = (source_row % segment_size) as usize;
 
             if index_in_segment < segment.len() {
                 visible_data.push(segment[index_in_segment].clone());
@@ -135,15 +679,7 @@ impl TableManager {
         // 转换数据为JS数组
         let js_array = Array::new();
         for row in &visible_data {
-            let js_row = Object::new();
-
-            for (key, value) in &row.cells {
-                let js_key = JsValue::from_str(key);
-                let js_value = JsValue::from_str(value);
-                Reflect::set(&js_row, &js_key, &js_value).unwrap();
-            }
-
-            js_array.push(&js_row);
+            js_array.push(&row_to_js_object(row));
         }
 
         // 调用渲染器的内容渲染方法
@@ -154,41 +690,115 @@ impl TableManager {
             scroll_top,
             start_row as f64,
         );
+
+        self.report_scroll_progress(scroll_left, scroll_top);
+    }
+
+    // 把当前滚动位置归一化为 0.0~1.0 后通知宿主，供迷你地图、"跳转到百分比"等功能使用
+    fn report_scroll_progress(&self, scroll_left: f64, scroll_top: f64) {
+        let Some(callback) = &self.scroll_progress_callback else {
+            return;
+        };
+
+        let total_width = self.renderer.get_total_width();
+        let total_height = self.renderer.get_total_height() - self.renderer.config.header_height;
+
+        let progress_x = if total_width > self.renderer.config.visible_width {
+            (scroll_left / (total_width - self.renderer.config.visible_width)).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let progress_y = if total_height > self.renderer.config.visible_height {
+            (scroll_top / (total_height - self.renderer.config.visible_height)).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+
+        let _ = callback.call2(
+            &JsValue::NULL,
+            &JsValue::from(progress_x),
+            &JsValue::from(progress_y),
+        );
     }
 
-    // 加载或获取某个数据段
-    fn get_or_load_segment(&mut self, segment_index: u32) -> &Vec<Row> {
-        if !self.data_cache.contains_key(&segment_index) {
+    // 加载或获取某个数据段：优先读取本地注入的数据集，其次调用异步段加载器
+    fn get_or_load_segment(&mut self, segment_index: u32) -> Vec<Row> {
+        if let Some(segment) = self.data_cache.borrow().get(&segment_index) {
+            return segment.clone();
+        }
+
+        if !self.local_rows.is_empty() {
             let start = segment_index * self.segment_size;
-            let end = (start + self.segment_size).min(self.renderer.config.rows);
-            let mut segment_data = Vec::with_capacity((end - start) as usize);
+            let end = (start + self.segment_size).min(self.local_rows.len() as u32);
+            let segment_data = if start < end {
+                self.local_rows[start as usize..end as usize].to_vec()
+            } else {
+                Vec::new()
+            };
+            self.insert_segment(segment_index, segment_data.clone());
+            return segment_data;
+        }
 
-            for i in start..end {
-                let mut row = Row {
-                    cells: HashMap::new(),
-                };
-                for j in 0..self.renderer.config.columns {
-                    row.cells
-                        .insert(format!("col_{}", j), format!("数据 {}-{}", i + 1, j + 1));
+        if let Some(loader) = self.segment_loader.clone() {
+            if !self.pending_segments.borrow().contains(&segment_index) {
+                self.pending_segments.borrow_mut().insert(segment_index);
+                if let Some(rows) = self.request_segment(loader, segment_index) {
+                    return rows;
                 }
-                segment_data.push(row);
             }
+        }
 
-            // 如果缓存过大，清理一些旧数据
-            if self.data_cache.len() > 10 {
-                self.clean_cache(segment_index);
-            }
+        Vec::new()
+    }
 
-            self.data_cache.insert(segment_index, segment_data);
+    // 调用 JS 段加载器：同步返回时把行数据写回缓存并直接返回给调用方，
+    // 异步 Promise 则返回 None，等 resolve 后才在回调里写回缓存
+    fn request_segment(&self, loader: js_sys::Function, segment_index: u32) -> Option<Vec<Row>> {
+        let columns = self.renderer.config.columns;
+        let cache = self.data_cache.clone();
+        let pending = self.pending_segments.clone();
+
+        let result = loader.call1(&JsValue::NULL, &JsValue::from(segment_index));
+        match result {
+            Ok(value) => {
+                if let Some(promise) = value.dyn_ref::<js_sys::Promise>() {
+                    let promise = promise.clone();
+                    wasm_bindgen_futures::spawn_local(async move {
+                        if let Ok(resolved) = wasm_bindgen_futures::JsFuture::from(promise).await {
+                            let rows = js_array_to_rows(&resolved, columns);
+                            cache.borrow_mut().insert(segment_index, rows);
+                        }
+                        pending.borrow_mut().remove(&segment_index);
+                    });
+                    None
+                } else {
+                    let rows = js_array_to_rows(&value, columns);
+                    cache.borrow_mut().insert(segment_index, rows.clone());
+                    pending.borrow_mut().remove(&segment_index);
+                    Some(rows)
+                }
+            }
+            Err(_) => {
+                pending.borrow_mut().remove(&segment_index);
+                None
+            }
         }
+    }
 
-        self.data_cache.get(&segment_index).unwrap()
+    // 将一个刚加载好的段写入缓存，并在必要时清理旧段
+    fn insert_segment(&mut self, segment_index: u32, segment_data: Vec<Row>) {
+        if self.data_cache.borrow().len() > 10 {
+            self.clean_cache(segment_index);
+        }
+        self.data_cache
+            .borrow_mut()
+            .insert(segment_index, segment_data);
     }
 
     // 清理缓存数据，保留当前需要的段和临近段
     fn clean_cache(&mut self, current_segment_index: u32) {
         let mut segments_to_remove = Vec::new();
-        for &segment_idx in self.data_cache.keys() {
+        for &segment_idx in self.data_cache.borrow().keys() {
             // 移除距离当前段超过2的段
             if segment_idx < current_segment_index.saturating_sub(2)
                 || segment_idx > current_segment_index + 2
@@ -199,24 +809,13 @@ impl TableManager {
 
         // 只保留最近使用的段
         if segments_to_remove.len() > 5 {
+            let mut cache = self.data_cache.borrow_mut();
             for segment_idx in segments_to_remove.iter().take(segments_to_remove.len() - 5) {
-                self.data_cache.remove(segment_idx);
+                cache.remove(segment_idx);
             }
         }
     }
 
-    // // 预热缓存
-    // #[wasm_bindgen]
-    // pub fn preload_segments(&mut self, center_segment_index: u32, amount: u32) {
-    //     let start = center_segment_index.saturating_sub(amount / 2);
-    //     let end = center_segment_index + (amount / 2);
-
-    //     for segment_idx in start..=end {
-    //         if segment_idx * self.segment_size < self.renderer.config.rows {
-    //             self.get_or_load_segment(segment_idx);
-    //         }
-    //     }
-    // }
     #[wasm_bindgen]
     pub fn configure_hd_canvas(
         &self,
@@ -242,19 +841,490 @@ impl TableManager {
 
         device_pixel_ratio
     }
+
+    // 将指定的行/列范围渲染到一个离屏画布并导出为 PNG data URL，供宿主保存或分享表格的任意切片
+    #[wasm_bindgen]
+    pub async fn export_png(
+        &self,
+        start_row: u32,
+        end_row: u32,
+        start_col: u32,
+        end_col: u32,
+    ) -> Result<String, JsValue> {
+        let end_row = end_row.min(self.renderer.config.rows.saturating_sub(1));
+        let end_col = end_col.min(self.renderer.config.columns.saturating_sub(1));
+        if start_row > end_row || start_col > end_col {
+            return Err(JsValue::from_str("export_png: 行/列范围为空"));
+        }
+
+        let dpr = web_sys::window()
+            .map(|w| w.device_pixel_ratio())
+            .unwrap_or(1.0);
+        let header_height = self.renderer.config.header_height;
+        let width = self.renderer.column_offsets[end_col as usize + 1]
+            - self.renderer.column_offsets[start_col as usize];
+        let height = header_height
+            + (self.renderer.y_offset(end_row + 1) - self.renderer.y_offset(start_row));
+
+        let canvas = web_sys::OffscreenCanvas::new((width * dpr) as u32, (height * dpr) as u32)?;
+        let ctx = canvas
+            .get_context("2d")?
+            .ok_or_else(|| JsValue::from_str("export_png: 无法获取 2d 上下文"))?
+            .dyn_into::<OffscreenCanvasRenderingContext2d>()?;
+        ctx.scale(dpr, dpr)?;
+
+        let rows = self.rows_in_range(start_row, end_row);
+        self.draw_export_range(&ctx, &rows, start_row, start_col, end_col);
+
+        let blob = wasm_bindgen_futures::JsFuture::from(canvas.convert_to_blob()?)
+            .await?
+            .dyn_into::<Blob>()?;
+        blob_to_data_url(&blob).await
+    }
+
+    // 取出指定范围内已知的行：优先读本地完整数据集，其次按段读已缓存的数据（不触发新的异步加载）
+    fn rows_in_range(&self, start_row: u32, end_row: u32) -> Vec<Row> {
+        if !self.local_rows.is_empty() {
+            return self.local_rows[start_row as usize..=end_row as usize].to_vec();
+        }
+
+        let cache = self.data_cache.borrow();
+        (start_row..=end_row)
+            .map(|row| {
+                let segment_idx = row / self.segment_size;
+                let index_in_segment = (row % self.segment_size) as usize;
+                cache
+                    .get(&segment_idx)
+                    .and_then(|segment| segment.get(index_in_segment))
+                    .cloned()
+                    .unwrap_or_else(|| Row {
+                        cells: HashMap::new(),
+                    })
+            })
+            .collect()
+    }
+
+    // 把表头和单元格内容绘制到导出画布上，不包含选区/搜索高亮等交互态
+    fn draw_export_range(
+        &self,
+        ctx: &OffscreenCanvasRenderingContext2d,
+        rows: &[Row],
+        start_row: u32,
+        start_col: u32,
+        end_col: u32,
+    ) {
+        let renderer = &self.renderer;
+        let header_height = renderer.config.header_height;
+        let col_offset = renderer.column_offsets[start_col as usize];
+        let row_offset = renderer.y_offset(start_row);
+        let width = renderer.column_offsets[end_col as usize + 1] - col_offset;
+        let height =
+            header_height + (renderer.y_offset(start_row + rows.len() as u32) - row_offset);
+
+        ctx.clear_rect(0.0, 0.0, width, height);
+
+        // 表头
+        ctx.set_fill_style_str("#f2f2f2");
+        ctx.fill_rect(0.0, 0.0, width, header_height);
+        ctx.set_fill_style_str("#333");
+        ctx.set_font("14px Arial");
+        ctx.set_text_align("center");
+        ctx.set_text_baseline("middle");
+        for col in start_col..=end_col {
+            let x = renderer.column_offsets[col as usize] - col_offset;
+            let col_width = renderer.column_widths[col as usize];
+            let text = format!("列 {}", col + 1);
+            ctx.fill_text(&text, x + col_width / 2.0, header_height / 2.0)
+                .unwrap();
+        }
+
+        // 内容背景与文本
+        for (row_idx, row) in rows.iter().enumerate() {
+            let absolute_row = start_row + row_idx as u32;
+            let row_height = renderer.row_height(absolute_row);
+            let y = header_height + (renderer.y_offset(absolute_row) - row_offset);
+            ctx.set_fill_style_str(if absolute_row.is_multiple_of(2) {
+                "#ffffff"
+            } else {
+                "#f9f9f9"
+            });
+            ctx.fill_rect(0.0, y, width, row_height);
+
+            ctx.set_fill_style_str("#333");
+            for col in start_col..=end_col {
+                let x = renderer.column_offsets[col as usize] - col_offset;
+                let col_width = renderer.column_widths[col as usize];
+                let key = format!("col_{}", col);
+                let text = row.cells.get(&key).cloned().unwrap_or_default();
+                ctx.fill_text(&text, x + col_width / 2.0, y + row_height / 2.0)
+                    .unwrap();
+            }
+        }
+
+        // 网格线
+        ctx.set_stroke_style_str("#ddd");
+        for col in start_col..=end_col + 1 {
+            let x = renderer.column_offsets[col as usize] - col_offset;
+            ctx.begin_path();
+            ctx.move_to(x, 0.0);
+            ctx.line_to(x, height);
+            ctx.stroke();
+        }
+        for row_idx in 0..=rows.len() as u32 {
+            let y = header_height + (renderer.y_offset(start_row + row_idx) - row_offset);
+            ctx.begin_path();
+            ctx.move_to(0.0, y);
+            ctx.line_to(width, y);
+            ctx.stroke();
+        }
+    }
+}
+
+// 把一个 Blob 读取为 base64 data URL（FileReader 的事件回调通过 Promise 包装后 await）
+async fn blob_to_data_url(blob: &Blob) -> Result<String, JsValue> {
+    let reader = FileReader::new()?;
+    let promise = js_sys::Promise::new(&mut |resolve, reject| {
+        let onload_reader = reader.clone();
+        let onload = Closure::once(Box::new(move || {
+            let result = onload_reader.result().unwrap_or(JsValue::NULL);
+            let _ = resolve.call1(&JsValue::NULL, &result);
+        }) as Box<dyn FnOnce()>);
+        reader.set_onload(Some(onload.as_ref().unchecked_ref()));
+        onload.forget();
+
+        let onerror = Closure::once(Box::new(move || {
+            let _ = reject.call0(&JsValue::NULL);
+        }) as Box<dyn FnOnce()>);
+        reader.set_onerror(Some(onerror.as_ref().unchecked_ref()));
+        onerror.forget();
+    });
+    reader.read_as_data_url(blob)?;
+
+    let result = wasm_bindgen_futures::JsFuture::from(promise).await?;
+    result
+        .as_string()
+        .ok_or_else(|| JsValue::from_str("export_png: FileReader 未返回字符串结果"))
+}
+
+// 测量一个单元格文本的最小宽度（最宽的不可断单词）和首选宽度（整串文本）
+fn measure_cell(ctx: &CanvasRenderingContext2d, text: &str) -> (f64, f64) {
+    let preferred = ctx.measure_text(text).map(|m| m.width()).unwrap_or(0.0);
+    let min_width = text
+        .split_whitespace()
+        .map(|token| ctx.measure_text(token).map(|m| m.width()).unwrap_or(0.0))
+        .fold(0.0_f64, f64::max);
+    (min_width, preferred)
+}
+
+// 给最小宽度、首选宽度各加一次内边距：首选宽度要先与未加padding的 min_w 取 max，
+// 否则单词内容等于最小宽度的列（数字、短 ID 等常见情况）会把已加过padding的 min_w 再加一次，多出一份 padding
+fn pad_widths(min_w: f64, pref_w: f64, padding: f64) -> (f64, f64) {
+    (min_w + padding, pref_w.max(min_w) + padding)
+}
+
+// 按单词贪心换行，数出把文本排进 max_width 所需的行数（空文本算一行）
+fn wrapped_line_count(ctx: &CanvasRenderingContext2d, text: &str, max_width: f64) -> u32 {
+    let space_width = ctx.measure_text(" ").map(|m| m.width()).unwrap_or(0.0);
+    let mut lines = 1u32;
+    let mut line_width = 0.0_f64;
+
+    for word in text.split_whitespace() {
+        let word_width = ctx.measure_text(word).map(|m| m.width()).unwrap_or(0.0);
+        let needed = if line_width > 0.0 {
+            line_width + space_width + word_width
+        } else {
+            word_width
+        };
+
+        if needed > max_width && line_width > 0.0 {
+            lines += 1;
+            line_width = word_width;
+        } else {
+            line_width = needed;
+        }
+    }
+
+    lines
 }
 
 // TableRenderer 结构体处理表格渲染逻辑
 #[wasm_bindgen]
 pub struct TableRenderer {
     config: TableConfig,
+    // 每列的宽度
+    column_widths: Vec<f64>,
+    // 每列的累积偏移量（长度为 columns + 1），用于二分查找可见列范围
+    column_offsets: Vec<f64>,
+    // 当前排序列及方向，None 表示未排序
+    sort_column: Option<u32>,
+    sort_ascending: bool,
+    // 当前选区的归一化矩形 (row_min, row_max, col_min, col_max)，均为可视坐标
+    selection: Option<(u32, u32, u32, u32)>,
+    // 当前搜索命中的单元格（可视坐标）
+    search_matches: HashSet<(u32, u32)>,
+    // 当前高亮的命中项（可视坐标）
+    active_match: Option<(u32, u32)>,
+    // 每行的高度，None 表示所有行都使用 config.cell_height
+    row_heights: Option<Vec<f64>>,
+    // 每行的累积偏移量（长度为 rows + 1），用于二分查找可见行范围，随 row_heights 一起重建
+    row_offsets: Option<Vec<f64>>,
 }
 
 #[wasm_bindgen]
 impl TableRenderer {
     #[wasm_bindgen(constructor)]
     pub fn new(config: TableConfig) -> TableRenderer {
-        TableRenderer { config }
+        let columns = config.columns as usize;
+        let column_widths = vec![config.cell_width; columns];
+        let column_offsets = Self::build_offsets(&column_widths);
+        TableRenderer {
+            config,
+            column_widths,
+            column_offsets,
+            sort_column: None,
+            sort_ascending: true,
+            selection: None,
+            search_matches: HashSet::new(),
+            active_match: None,
+            row_heights: None,
+            row_offsets: None,
+        }
+    }
+
+    // 按给定的行高数组重新设置每一行的高度，并重建累积偏移量
+    #[wasm_bindgen]
+    pub fn set_row_heights(&mut self, heights: Vec<f64>) {
+        self.row_offsets = Some(Self::build_offsets(&heights));
+        self.row_heights = Some(heights);
+    }
+
+    // 取消自定义行高，恢复为统一的 config.cell_height
+    #[wasm_bindgen]
+    pub fn clear_row_heights(&mut self) {
+        self.row_heights = None;
+        self.row_offsets = None;
+    }
+
+    // 根据每行数据自动测量行高：按列宽对每个单元格文本分词换行，
+    // 用其中换行数最多的单元格（不超过 max_lines）决定整行高度
+    #[wasm_bindgen]
+    pub fn auto_size_rows(
+        &mut self,
+        ctx: &CanvasRenderingContext2d,
+        rows: &JsValue,
+        max_lines: u32,
+    ) {
+        const LINE_HEIGHT: f64 = 18.0;
+        const CELL_PADDING: f64 = 8.0;
+
+        let max_lines = max_lines.max(1);
+        ctx.set_font("14px Arial");
+
+        let columns = self.config.columns as usize;
+        let keys: Vec<String> = (0..columns).map(|c| format!("col_{}", c)).collect();
+
+        let heights = Array::from(rows)
+            .iter()
+            .map(|row| {
+                let mut line_count = 1u32;
+                for (col, key) in keys.iter().enumerate() {
+                    let col_width = self
+                        .column_widths
+                        .get(col)
+                        .copied()
+                        .unwrap_or(self.config.cell_width)
+                        - CELL_PADDING;
+                    let cell_value =
+                        Reflect::get(&row, &JsValue::from_str(key)).unwrap_or(JsValue::undefined());
+                    let text = cell_value.as_string().unwrap_or_default();
+                    let lines = wrapped_line_count(ctx, &text, col_width.max(1.0)).min(max_lines);
+                    if lines > line_count {
+                        line_count = lines;
+                    }
+                }
+                (line_count as f64 * LINE_HEIGHT + CELL_PADDING).max(self.config.cell_height)
+            })
+            .collect();
+
+        self.set_row_heights(heights);
+    }
+
+    // 第 row 行的高度：已设置自定义行高则查表，否则退回统一的 cell_height
+    fn row_height(&self, row: u32) -> f64 {
+        match &self.row_heights {
+            Some(heights) => heights
+                .get(row as usize)
+                .copied()
+                .unwrap_or(self.config.cell_height),
+            None => self.config.cell_height,
+        }
+    }
+
+    // 第 row 行顶部相对内容区域起点的 y 偏移量：已设置自定义行高则查累积偏移量表，否则按统一行高计算
+    fn y_offset(&self, row: u32) -> f64 {
+        match &self.row_offsets {
+            Some(offsets) => offsets
+                .get(row as usize)
+                .copied()
+                .unwrap_or_else(|| *offsets.last().unwrap_or(&0.0)),
+            None => row as f64 * self.config.cell_height,
+        }
+    }
+
+    // 在累积行偏移量数组中二分查找给定 y 坐标所在的行；未设置自定义行高时退回除法
+    fn row_at_offset(&self, y: f64) -> u32 {
+        match &self.row_offsets {
+            Some(offsets) if offsets.len() > 1 => match offsets
+                .binary_search_by(|offset| offset.partial_cmp(&y).unwrap())
+            {
+                Ok(idx) => (idx as u32).min(self.config.rows.saturating_sub(1)),
+                Err(idx) => (idx.saturating_sub(1) as u32).min(self.config.rows.saturating_sub(1)),
+            },
+            _ => ((y / self.config.cell_height).floor() as u32)
+                .min(self.config.rows.saturating_sub(1)),
+        }
+    }
+
+    // 设置当前选区的归一化矩形，供内容渲染时绘制高亮
+    fn set_selection(&mut self, rect: (u32, u32, u32, u32)) {
+        self.selection = Some(rect);
+    }
+
+    // 清除选区高亮
+    fn clear_selection(&mut self) {
+        self.selection = None;
+    }
+
+    // 设置搜索命中集合与当前高亮的命中项（均为可视坐标）
+    fn set_search_matches(&mut self, matches: HashSet<(u32, u32)>, active: Option<(u32, u32)>) {
+        self.search_matches = matches;
+        self.active_match = active;
+    }
+
+    // 按给定的列宽数组重新设置每一列的宽度，并重建累积偏移量
+    #[wasm_bindgen]
+    pub fn set_column_widths(&mut self, widths: Vec<f64>) {
+        self.column_widths = widths;
+        self.column_offsets = Self::build_offsets(&self.column_widths);
+    }
+
+    // 设置当前排序列及方向，表头会在该列标签旁绘制 ▲/▼ 指示符
+    fn set_sort_indicator(&mut self, col: u32, ascending: bool) {
+        self.sort_column = Some(col);
+        self.sort_ascending = ascending;
+    }
+
+    // 清除排序指示符：数据重新加载/追加/插入或切换加载模式后，之前的排序结果已失效，表头不应再显示 ▲/▼
+    fn clear_sort_indicator(&mut self) {
+        self.sort_column = None;
+    }
+
+    // 设置单列宽度
+    #[wasm_bindgen]
+    pub fn set_column_width(&mut self, col: u32, width: f64) {
+        if let Some(w) = self.column_widths.get_mut(col as usize) {
+            *w = width;
+            self.column_offsets = Self::build_offsets(&self.column_widths);
+        }
+    }
+
+    // 根据每行数据自动计算列宽：仿 CSS 表格布局算法，
+    // 先求每列的最小宽度（最宽的不可断词）与首选宽度（整串文本），
+    // 若首选宽度总和能放进可视区域就直接用首选宽度，否则按 (首选-最小) 的比例分配剩余空间
+    #[wasm_bindgen]
+    pub fn auto_size_columns(&mut self, ctx: &CanvasRenderingContext2d, rows: &JsValue) {
+        let columns = self.config.columns as usize;
+        if columns == 0 {
+            return;
+        }
+
+        ctx.set_font("14px Arial");
+
+        let mut min_widths = vec![0.0_f64; columns];
+        let mut preferred_widths = vec![0.0_f64; columns];
+
+        for col in 0..columns {
+            let header_text = format!("列 {}", col + 1);
+            let (min_w, pref_w) = measure_cell(ctx, &header_text);
+            min_widths[col] = min_w;
+            preferred_widths[col] = pref_w;
+        }
+
+        let keys: Vec<String> = (0..columns).map(|c| format!("col_{}", c)).collect();
+        for row in Array::from(rows).iter() {
+            for (col, key) in keys.iter().enumerate() {
+                let cell_value =
+                    Reflect::get(&row, &JsValue::from_str(key)).unwrap_or(JsValue::undefined());
+                let text = cell_value.as_string().unwrap_or_default();
+                let (min_w, pref_w) = measure_cell(ctx, &text);
+                if min_w > min_widths[col] {
+                    min_widths[col] = min_w;
+                }
+                if pref_w > preferred_widths[col] {
+                    preferred_widths[col] = pref_w;
+                }
+            }
+        }
+
+        const CELL_PADDING: f64 = 16.0;
+        for col in 0..columns {
+            let (min_w, pref_w) = pad_widths(min_widths[col], preferred_widths[col], CELL_PADDING);
+            min_widths[col] = min_w;
+            preferred_widths[col] = pref_w;
+        }
+
+        let total_preferred: f64 = preferred_widths.iter().sum();
+        let widths = if total_preferred <= self.config.visible_width {
+            preferred_widths
+        } else {
+            let total_min: f64 = min_widths.iter().sum();
+            let leftover = (self.config.visible_width - total_min).max(0.0);
+            let total_flex: f64 = min_widths
+                .iter()
+                .zip(preferred_widths.iter())
+                .map(|(min_w, pref_w)| (pref_w - min_w).max(0.0))
+                .sum();
+
+            (0..columns)
+                .map(|col| {
+                    let flex = (preferred_widths[col] - min_widths[col]).max(0.0);
+                    let share = if total_flex > 0.0 {
+                        leftover * (flex / total_flex)
+                    } else {
+                        0.0
+                    };
+                    min_widths[col] + share
+                })
+                .collect()
+        };
+
+        self.set_column_widths(widths);
+    }
+
+    // 从列宽数组构建累积偏移量数组，offsets[i] 是第 i 列的起始 x 坐标
+    fn build_offsets(widths: &[f64]) -> Vec<f64> {
+        let mut offsets = Vec::with_capacity(widths.len() + 1);
+        let mut acc = 0.0;
+        offsets.push(acc);
+        for w in widths {
+            acc += w;
+            offsets.push(acc);
+        }
+        offsets
+    }
+
+    // 在累积偏移量数组中二分查找给定 x 坐标所在的列
+    fn col_at_offset(&self, x: f64) -> u32 {
+        if self.column_offsets.len() <= 1 {
+            return 0;
+        }
+        match self
+            .column_offsets
+            .binary_search_by(|offset| offset.partial_cmp(&x).unwrap())
+        {
+            Ok(idx) => (idx as u32).min(self.config.columns - 1),
+            Err(idx) => (idx.saturating_sub(1) as u32).min(self.config.columns - 1),
+        }
     }
 
     // 渲染表头
@@ -290,16 +1360,18 @@ impl TableRenderer {
 
         // 绘制每一列的表头
         for col in start_col..=end_col {
-            let x = col as f64 * self.config.cell_width - scroll_left;
-
-            // 绘制文本 - 显示从1开始的列编号
-            let text = format!("列 {}", col + 1);
-            ctx.fill_text(
-                &text,
-                x + self.config.cell_width / 2.0,
-                self.config.header_height / 2.0,
-            )
-            .unwrap();
+            let x = self.column_offsets[col as usize] - scroll_left;
+            let col_width = self.column_widths[col as usize];
+
+            // 绘制文本 - 显示从1开始的列编号，当前排序列附带 ▲/▼ 指示符
+            let text = if self.sort_column == Some(col) {
+                let arrow = if self.sort_ascending { "▲" } else { "▼" };
+                format!("列 {} {}", col + 1, arrow)
+            } else {
+                format!("列 {}", col + 1)
+            };
+            ctx.fill_text(&text, x + col_width / 2.0, self.config.header_height / 2.0)
+                .unwrap();
         }
 
         // 优化批量绘制表头分隔线
@@ -307,7 +1379,7 @@ impl TableRenderer {
         ctx.begin_path();
 
         for col in start_col..=end_col + 1 {
-            let x = col as f64 * self.config.cell_width - scroll_left;
+            let x = self.column_offsets[col as usize] - scroll_left;
             ctx.move_to(x, 0.0);
             ctx.line_to(x, self.config.header_height);
         }
@@ -322,20 +1394,26 @@ impl TableRenderer {
     // 计算总宽度
     #[wasm_bindgen]
     pub fn get_total_width(&self) -> f64 {
-        self.config.columns as f64 * self.config.cell_width
+        *self.column_offsets.last().unwrap_or(&0.0)
     }
 
     // 计算总高度
     #[wasm_bindgen]
     pub fn get_total_height(&self) -> f64 {
-        // 分段计算避免精度问题
-        let rows_per_segment = 10_000_000;
-        let full_segments = self.config.rows / rows_per_segment;
-        let remaining_rows = self.config.rows % rows_per_segment;
+        let content_height = match &self.row_offsets {
+            Some(offsets) => *offsets.last().unwrap_or(&0.0),
+            None => {
+                // 分段计算避免精度问题
+                let rows_per_segment = 10_000_000;
+                let full_segments = self.config.rows / rows_per_segment;
+                let remaining_rows = self.config.rows % rows_per_segment;
+
+                (full_segments as f64 * rows_per_segment as f64 * self.config.cell_height)
+                    + (remaining_rows as f64 * self.config.cell_height)
+            }
+        };
 
-        (full_segments as f64 * rows_per_segment as f64 * self.config.cell_height)
-            + (remaining_rows as f64 * self.config.cell_height)
-            + self.config.header_height
+        content_height + self.config.header_height
     }
 
     // 添加仅渲染内容的方法
@@ -356,28 +1434,83 @@ impl TableRenderer {
             self.config.visible_height,
         );
 
-        // 计算可见区域中的起始/结束列索引
-        let start_col = (scroll_left / self.config.cell_width).floor() as u32;
-        let end_col =
-            ((scroll_left + self.config.visible_width) / self.config.cell_width).floor() as u32;
-        let end_col = end_col.min(self.config.columns - 1);
+        // 计算可见区域中的起始/结束列索引（对累积偏移量数组做二分查找）
+        let start_col = self.col_at_offset(scroll_left);
+        let end_col = self.col_at_offset(scroll_left + self.config.visible_width);
 
-        // 计算可见区域中的起始/结束行索引
-        let visible_rows = (self.config.visible_height / self.config.cell_height).ceil() + 1.0;
-        let start_row = visible_start_row;
-        let end_row = (start_row + visible_rows).min(self.config.rows as f64 - 1.0);
+        // 计算可见区域中的起始/结束行索引（对累积行偏移量数组做二分查找，支持可变行高）
+        let start_row = visible_start_row as u32;
+        let end_row = self
+            .row_at_offset(scroll_top + self.config.visible_height)
+            .min(self.config.rows.saturating_sub(1));
 
         // 绘制表格内容 - 注意这里使用了专门针对内容区域的渲染方法
         self.render_content_cells(
             canvas_ctx,
             data,
-            0,                            // 在数据数组中的起始索引是0
-            (end_row - start_row) as u32, // 数据数组的结束索引
+            0,                                 // 在数据数组中的起始索引是0
+            end_row.saturating_sub(start_row), // 数据数组的结束索引
             start_col,
             end_col,
             scroll_left,
-            scroll_top - (start_row * self.config.cell_height), // 调整滚动位置
+            scroll_top - self.y_offset(start_row), // 调整滚动位置
+            start_row,                             // 数据数组第 0 行对应的绝对行号，用于比对选区
         );
+
+        self.render_scrollbars(canvas_ctx, scroll_left, scroll_top);
+    }
+
+    // 当内容超出可视区域时，在内容之上叠加绘制半透明的圆角滚动条
+    fn render_scrollbars(&self, ctx: &CanvasRenderingContext2d, scroll_left: f64, scroll_top: f64) {
+        const MIN_THUMB_LENGTH: f64 = 20.0;
+        const THICKNESS: f64 = 8.0;
+        const MARGIN: f64 = 2.0;
+
+        let total_width = self.get_total_width();
+        let total_height = self.get_total_height() - self.config.header_height;
+
+        ctx.set_fill_style_str("rgba(0, 0, 0, 0.3)");
+
+        if total_height > self.config.visible_height {
+            let thumb_height = (self.config.visible_height
+                * (self.config.visible_height / total_height))
+                .max(MIN_THUMB_LENGTH);
+            let max_scroll = (total_height - self.config.visible_height).max(1.0);
+            let thumb_top = (scroll_top / max_scroll) * (self.config.visible_height - thumb_height);
+            let x = self.config.visible_width - THICKNESS - MARGIN;
+            Self::fill_rounded_rect(ctx, x, thumb_top, THICKNESS, thumb_height, THICKNESS / 2.0);
+        }
+
+        if total_width > self.config.visible_width {
+            let thumb_width = (self.config.visible_width
+                * (self.config.visible_width / total_width))
+                .max(MIN_THUMB_LENGTH);
+            let max_scroll = (total_width - self.config.visible_width).max(1.0);
+            let thumb_left = (scroll_left / max_scroll) * (self.config.visible_width - thumb_width);
+            let y = self.config.visible_height - THICKNESS - MARGIN;
+            Self::fill_rounded_rect(ctx, thumb_left, y, thumb_width, THICKNESS, THICKNESS / 2.0);
+        }
+    }
+
+    // 绘制一个填充的圆角矩形
+    fn fill_rounded_rect(
+        ctx: &CanvasRenderingContext2d,
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+        radius: f64,
+    ) {
+        ctx.begin_path();
+        ctx.move_to(x + radius, y);
+        ctx.arc_to(x + width, y, x + width, y + height, radius)
+            .unwrap();
+        ctx.arc_to(x + width, y + height, x, y + height, radius)
+            .unwrap();
+        ctx.arc_to(x, y + height, x, y, radius).unwrap();
+        ctx.arc_to(x, y, x + width, y, radius).unwrap();
+        ctx.close_path();
+        ctx.fill();
     }
 
     // 仅渲染表格单元格内容，不包含表头区域
@@ -391,9 +1524,12 @@ impl TableRenderer {
         end_col: u32,
         scroll_left: f64,
         adjusted_scroll_top: f64,
+        row_offset: u32,
     ) {
         // 从 JS 值转换数据
         let data_array = Array::from(data_js);
+        // 数据数组第 0 行顶部相对内容区域起点的 y 偏移量，后面每行的 y 都相对它计算
+        let base_offset = self.y_offset(row_offset);
 
         // 先批量绘制偶数行背景
         ctx.set_fill_style_str("#ffffff");
@@ -401,20 +1537,21 @@ impl TableRenderer {
             if row_idx >= data_array.length() as u32 {
                 break; // 这行很重要，防止访问不存在的数据
             }
-            let actual_row = row_idx;
-            if actual_row % 2 != 0 {
+            let absolute_row = row_offset + row_idx;
+            if absolute_row % 2 != 0 {
                 continue;
             }
 
             // 不再加上表头高度，因为表头已经在单独的Canvas中
-            let y = (row_idx as f64 * self.config.cell_height) - adjusted_scroll_top;
+            let row_height = self.row_height(absolute_row);
+            let y = (self.y_offset(absolute_row) - base_offset) - adjusted_scroll_top;
 
             // 如果行不可见，跳过
-            if y + self.config.cell_height < 0.0 || y > self.config.visible_height {
+            if y + row_height < 0.0 || y > self.config.visible_height {
                 continue;
             }
 
-            ctx.fill_rect(0.0, y, self.config.visible_width, self.config.cell_height);
+            ctx.fill_rect(0.0, y, self.config.visible_width, row_height);
         }
 
         // 再批量绘制奇数行背景
@@ -423,20 +1560,86 @@ impl TableRenderer {
             if row_idx >= data_array.length() as u32 {
                 break; // 这行很重要，防止访问不存在的数据
             }
-            let actual_row = row_idx;
-            if actual_row % 2 == 0 {
+            let absolute_row = row_offset + row_idx;
+            if absolute_row % 2 == 0 {
                 continue;
             }
 
             // 不再加上表头高度
-            let y = (row_idx as f64 * self.config.cell_height) - adjusted_scroll_top;
+            let row_height = self.row_height(absolute_row);
+            let y = (self.y_offset(absolute_row) - base_offset) - adjusted_scroll_top;
 
             // 如果行不可见，跳过
-            if y + self.config.cell_height < 0.0 || y > self.config.visible_height {
+            if y + row_height < 0.0 || y > self.config.visible_height {
                 continue;
             }
 
-            ctx.fill_rect(0.0, y, self.config.visible_width, self.config.cell_height);
+            ctx.fill_rect(0.0, y, self.config.visible_width, row_height);
+        }
+
+        // 绘制搜索命中高亮（黄色背景，当前命中项为橙色），同样在文字之前、背景之后
+        if !self.search_matches.is_empty() || self.active_match.is_some() {
+            for row_idx in data_start_row..=data_end_row {
+                if row_idx >= data_array.length() as u32 {
+                    break;
+                }
+                let absolute_row = row_offset + row_idx;
+
+                let row_height = self.row_height(absolute_row);
+                let y = (self.y_offset(absolute_row) - base_offset) - adjusted_scroll_top;
+                if y + row_height < 0.0 || y > self.config.visible_height {
+                    continue;
+                }
+
+                for col in start_col..=end_col {
+                    let x = self.column_offsets[col as usize] - scroll_left;
+                    let col_width = self.column_widths[col as usize];
+                    if x + col_width < 0.0 || x > self.config.visible_width {
+                        continue;
+                    }
+
+                    let coord = (absolute_row, col);
+                    if Some(coord) == self.active_match {
+                        ctx.set_fill_style_str("#ff9800");
+                        ctx.fill_rect(x, y, col_width, row_height);
+                    } else if self.search_matches.contains(&coord) {
+                        ctx.set_fill_style_str("#fff59d");
+                        ctx.fill_rect(x, y, col_width, row_height);
+                    }
+                }
+            }
+        }
+
+        // 绘制选区高亮（半透明蓝色），在文字之前、背景之后
+        if let Some((row_min, row_max, col_min, col_max)) = self.selection {
+            ctx.set_fill_style_str("rgba(33, 150, 243, 0.25)");
+            for row_idx in data_start_row..=data_end_row {
+                if row_idx >= data_array.length() as u32 {
+                    break;
+                }
+                let absolute_row = row_offset + row_idx;
+                if absolute_row < row_min || absolute_row > row_max {
+                    continue;
+                }
+
+                let row_height = self.row_height(absolute_row);
+                let y = (self.y_offset(absolute_row) - base_offset) - adjusted_scroll_top;
+                if y + row_height < 0.0 || y > self.config.visible_height {
+                    continue;
+                }
+
+                for col in start_col..=end_col {
+                    if col < col_min || col > col_max {
+                        continue;
+                    }
+                    let x = self.column_offsets[col as usize] - scroll_left;
+                    let col_width = self.column_widths[col as usize];
+                    if x + col_width < 0.0 || x > self.config.visible_width {
+                        continue;
+                    }
+                    ctx.fill_rect(x, y, col_width, row_height);
+                }
+            }
         }
 
         // 绘制单元格内容
@@ -451,20 +1654,23 @@ impl TableRenderer {
             }
 
             let row_data = data_array.get(row_idx);
+            let absolute_row = row_offset + row_idx;
 
             // 不再加上表头高度
-            let y = (row_idx as f64 * self.config.cell_height) - adjusted_scroll_top;
+            let row_height = self.row_height(absolute_row);
+            let y = (self.y_offset(absolute_row) - base_offset) - adjusted_scroll_top;
 
             // 如果行不可见，跳过
-            if y + self.config.cell_height < 0.0 || y > self.config.visible_height {
+            if y + row_height < 0.0 || y > self.config.visible_height {
                 continue;
             }
 
             for col in start_col..=end_col {
-                let x = col as f64 * self.config.cell_width - scroll_left;
+                let x = self.column_offsets[col as usize] - scroll_left;
+                let col_width = self.column_widths[col as usize];
 
                 // 如果单元格在水平方向不可见，跳过
-                if x + self.config.cell_width < 0.0 || x > self.config.visible_width {
+                if x + col_width < 0.0 || x > self.config.visible_width {
                     continue;
                 }
 
@@ -481,12 +1687,8 @@ impl TableRenderer {
                 };
 
                 // 绘制单元格文本
-                ctx.fill_text(
-                    &text,
-                    x + self.config.cell_width / 2.0,
-                    y + self.config.cell_height / 2.0,
-                )
-                .unwrap();
+                ctx.fill_text(&text, x + col_width / 2.0, y + row_height / 2.0)
+                    .unwrap();
             }
         }
 
@@ -502,26 +1704,47 @@ impl TableRenderer {
                 break;
             }
 
+            let absolute_row = row_offset + row_idx;
+
             // 计算行的Y坐标
-            let y = (row_idx as f64 * self.config.cell_height) - adjusted_scroll_top;
+            let row_height = self.row_height(absolute_row);
+            let y = (self.y_offset(absolute_row) - base_offset) - adjusted_scroll_top;
 
             // 如果行不可见，跳过
-            if y + self.config.cell_height < 0.0 || y > self.config.visible_height {
+            if y + row_height < 0.0 || y > self.config.visible_height {
                 continue;
             }
 
             for col in start_col..=end_col {
-                let x = col as f64 * self.config.cell_width - scroll_left;
+                let x = self.column_offsets[col as usize] - scroll_left;
+                let col_width = self.column_widths[col as usize];
 
                 // 如果单元格在水平方向不可见，跳过
-                if x + self.config.cell_width < 0.0 || x > self.config.visible_width {
+                if x + col_width < 0.0 || x > self.config.visible_width {
                     continue;
                 }
 
                 // 为每个单元格单独绘制一个矩形边框
-                ctx.stroke_rect(x, y, self.config.cell_width, self.config.cell_height);
+                ctx.stroke_rect(x, y, col_width, row_height);
             }
         }
+
+        // 沿选区外边界绘制一条加粗边框
+        if let Some((row_min, row_max, col_min, col_max)) = self.selection {
+            if col_min as usize >= self.column_offsets.len() - 1 {
+                return;
+            }
+            let rect_x = self.column_offsets[col_min as usize] - scroll_left;
+            let rect_width =
+                self.column_offsets[col_max as usize + 1] - self.column_offsets[col_min as usize];
+            let rect_y = (self.y_offset(row_min) - base_offset) - adjusted_scroll_top;
+            let rect_height = self.y_offset(row_max + 1) - self.y_offset(row_min);
+
+            ctx.set_stroke_style_str("#1976d2");
+            ctx.set_line_width(2.0);
+            ctx.stroke_rect(rect_x, rect_y, rect_width, rect_height);
+            ctx.set_line_width(1.0);
+        }
     }
 }
 
@@ -534,3 +1757,23 @@ pub fn start() {
 
     log("WASM 初始化完成");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::pad_widths;
+
+    #[test]
+    fn pad_widths_single_token_matches_min_width() {
+        // 单词内容（数字、短 ID）preferred == min，padding 只应加一次
+        let (min_w, pref_w) = pad_widths(40.0, 40.0, 16.0);
+        assert_eq!(min_w, 56.0);
+        assert_eq!(pref_w, 56.0);
+    }
+
+    #[test]
+    fn pad_widths_multi_word_keeps_preferred_wider() {
+        let (min_w, pref_w) = pad_widths(20.0, 60.0, 16.0);
+        assert_eq!(min_w, 36.0);
+        assert_eq!(pref_w, 76.0);
+    }
+}